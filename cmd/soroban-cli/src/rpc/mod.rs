@@ -1,12 +1,15 @@
-use jsonrpsee_core::{self, client::ClientT, rpc_params};
-use jsonrpsee_http_client::{types, HeaderMap, HttpClient, HttpClientBuilder};
 use soroban_env_host::xdr::{Error as XdrError, LedgerKey, TransactionEnvelope, WriteXdr};
-use std::{
-    collections,
-    time::{Duration, Instant},
-};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
 use tokio::time::sleep;
 
+mod pooled;
+mod transport;
+mod ws;
+pub use pooled::{PooledClient, Retention};
+pub use transport::{HttpTransport, MockTransport, Mocks, Transport};
+pub use ws::WsClient;
+
 const VERSION: Option<&str> = option_env!("CARGO_PKG_VERSION");
 
 #[derive(thiserror::Error, Debug)]
@@ -21,10 +24,20 @@ pub enum Error {
     TransactionSubmissionFailed,
     #[error("expected transaction status: {0}")]
     UnexpectedTransactionStatus(String),
-    #[error("transaction submission timeout")]
-    TransactionSubmissionTimeout,
+    #[error("transaction submission timeout, last status: {}", .0.status)]
+    TransactionSubmissionTimeout(Box<GetTransactionStatusResponse>),
     #[error("transaction simulation failed: {0}")]
     TransactionSimulationFailed(String),
+    #[error("websocket error: {0}")]
+    Ws(String),
+    #[error("websocket connection closed")]
+    WsClosed,
+    #[error("websocket keepalive pong timed out")]
+    WsPongTimeout,
+    #[error("no mock response registered for method: {0}")]
+    MockNotFound(String),
+    #[error("no healthy endpoint available")]
+    NoHealthyEndpoint,
 }
 
 // TODO: this should also be used by serve
@@ -135,45 +148,172 @@ pub enum EventType {
     System,
 }
 
+/// Exponential backoff for the confirmation polling loop: wait `initial`
+/// before the first re-poll and double the wait after each attempt, never
+/// exceeding `max`.
+#[derive(Clone, Copy, Debug)]
+pub struct Backoff {
+    pub initial: Duration,
+    pub max: Duration,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_millis(500),
+            max: Duration::from_secs(8),
+        }
+    }
+}
+
+/// How long, and how eagerly, [`Client::send_transaction_with_config`] waits
+/// for a submitted transaction to leave the `pending` state.
+#[derive(Clone, Copy, Debug)]
+pub struct ConfirmationConfig {
+    /// Give up after this long and surface the last observed status.
+    pub timeout: Duration,
+    /// Fixed delay between polls when `backoff` is `None`.
+    pub poll_interval: Duration,
+    /// Optional exponential backoff; overrides `poll_interval` when set.
+    pub backoff: Option<Backoff>,
+}
+
+impl Default for ConfirmationConfig {
+    fn default() -> Self {
+        // The historical defaults: poll once a second, give up after ten.
+        Self {
+            timeout: Duration::from_secs(10),
+            poll_interval: Duration::from_secs(1),
+            backoff: None,
+        }
+    }
+}
+
 pub struct Client {
     base_url: String,
+    transport: Box<dyn Transport>,
+    // The WS connection is owned by a single background task (see [`WsClient`]);
+    // we share that one connection across every subscription rather than
+    // opening a socket per call, so it is created lazily on first subscribe.
+    ws: OnceLock<WsClient>,
 }
 
 impl Client {
     pub fn new(base_url: &str) -> Self {
         Self {
             base_url: base_url.to_string(),
+            transport: Box::new(HttpTransport::new(base_url)),
+            ws: OnceLock::new(),
         }
     }
 
-    fn client(&self) -> Result<HttpClient, Error> {
-        let url = self.base_url.clone();
-        let mut headers = HeaderMap::new();
-        headers.insert("X-Client-Name", "soroban-cli".parse().unwrap());
-        let version = VERSION.unwrap_or("devel");
-        headers.insert("X-Client-Version", version.parse().unwrap());
-        // TODO: We should consider migrating the server subcommand to jsonrpsee
-        Ok(HttpClientBuilder::default()
-            .set_headers(headers)
-            .build(url)?)
+    /// Construct a client backed by an in-memory [`Mocks`] table instead of a
+    /// live RPC server, for deterministic tests and downstream fixtures.
+    pub fn new_mock(mocks: Mocks) -> Self {
+        Self {
+            base_url: String::new(),
+            transport: Box::new(MockTransport::new(mocks)),
+            ws: OnceLock::new(),
+        }
+    }
+
+    /// Dispatch `method` through the configured transport and decode the result
+    /// into `T`.
+    async fn request<T: serde::de::DeserializeOwned>(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<T, Error> {
+        let value = self.transport.request(method, params).await?;
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Derive the WebSocket endpoint from the configured HTTP base url.
+    fn ws_url(&self) -> String {
+        if let Some(rest) = self.base_url.strip_prefix("https://") {
+            format!("wss://{rest}")
+        } else if let Some(rest) = self.base_url.strip_prefix("http://") {
+            format!("ws://{rest}")
+        } else {
+            self.base_url.clone()
+        }
+    }
+
+    /// The single, lazily-established [`WsClient`] shared by every
+    /// subscription on this `Client`.
+    fn ws_client(&self) -> &WsClient {
+        self.ws.get_or_init(|| WsClient::new(&self.ws_url()))
+    }
+
+    /// Open a live subscription to contract/system events matching `filters`,
+    /// yielding each `Event` as the server pushes it instead of requiring
+    /// repeated `get_events` polling.
+    pub async fn subscribe_events(
+        &self,
+        filters: serde_json::Value,
+    ) -> Result<impl futures::stream::Stream<Item = Result<Event, Error>>, Error> {
+        self.ws_client().subscribe_events(filters).await
+    }
+
+    /// Open a live subscription to the status of transaction `id`, yielding each
+    /// `GetTransactionStatusResponse` as it changes instead of requiring
+    /// repeated `get_transaction_status` polling.
+    pub async fn subscribe_transaction_status(
+        &self,
+        id: &str,
+    ) -> Result<impl futures::stream::Stream<Item = Result<GetTransactionStatusResponse, Error>>, Error>
+    {
+        self.ws_client().subscribe_transaction_status(id).await
     }
 
     pub async fn get_account(&self, account_id: &str) -> Result<GetAccountResponse, Error> {
-        Ok(self
-            .client()?
-            .request("getAccount", rpc_params![account_id])
-            .await?)
+        self.request("getAccount", serde_json::json!([account_id]))
+            .await
     }
 
+    /// Submit `tx` and poll until it succeeds, fails, or the default
+    /// confirmation timeout elapses. Status reporting is left to
+    /// [`Client::send_transaction_with_config`]; this wrapper just prints the
+    /// final `success` to stderr as the CLI has always done.
     pub async fn send_transaction(
         &self,
         tx: &TransactionEnvelope,
     ) -> Result<Vec<TransactionStatusResult>, Error> {
-        let client = self.client()?;
-        let SendTransactionResponse { id, status } = client
-            .request("sendTransaction", rpc_params![tx.to_xdr_base64()?])
-            .await
-            .map_err(|_| Error::TransactionSubmissionFailed)?;
+        self.send_transaction_with_config(tx, &ConfirmationConfig::default(), |response| {
+            let succeeded = response.status == "success";
+            async move {
+                if succeeded {
+                    eprintln!("success");
+                }
+            }
+        })
+        .await
+    }
+
+    /// Submit `tx` and poll its status according to `config`, invoking
+    /// `on_status` with each observed [`GetTransactionStatusResponse`] so the
+    /// caller — not the library — decides how to report progress.
+    ///
+    /// On timeout the last response (including `result_xdr`/`result_meta_xdr`)
+    /// is returned inside [`Error::TransactionSubmissionTimeout`] rather than
+    /// being discarded.
+    pub async fn send_transaction_with_config<F, Fut>(
+        &self,
+        tx: &TransactionEnvelope,
+        config: &ConfirmationConfig,
+        mut on_status: F,
+    ) -> Result<Vec<TransactionStatusResult>, Error>
+    where
+        F: FnMut(&GetTransactionStatusResponse) -> Fut,
+        Fut: std::future::Future<Output = ()>,
+    {
+        // Keep the transport-level error (e.g. `JsonRpc` for a node that's down)
+        // rather than collapsing it to `TransactionSubmissionFailed`: the pool
+        // relies on that distinction to fail over a never-submitted transaction
+        // while leaving a deterministically-failed one alone.
+        let SendTransactionResponse { id, status } = self
+            .request("sendTransaction", serde_json::json!([tx.to_xdr_base64()?]))
+            .await?;
 
         if status == "error" {
             return Err(Error::TransactionSubmissionFailed);
@@ -182,14 +322,14 @@ impl Client {
 
         // Poll the transaction status
         let start = Instant::now();
+        let mut delay = config
+            .backoff
+            .map_or(config.poll_interval, |backoff| backoff.initial);
         loop {
             let response = self.get_transaction_status(&id).await?;
+            on_status(&response).await;
             match response.status.as_str() {
-                "success" => {
-                    // TODO: the caller should probably be printing this
-                    eprintln!("{}", response.status);
-                    return Ok(response.results);
-                }
+                "success" => return Ok(response.results),
                 "error" => {
                     // TODO: provide a more elaborate error
                     return Err(Error::TransactionSubmissionFailed);
@@ -199,12 +339,13 @@ impl Client {
                     return Err(Error::UnexpectedTransactionStatus(response.status));
                 }
             };
-            let duration = start.elapsed();
-            // TODO: parameterize the timeout instead of using a magic constant
-            if duration.as_secs() > 10 {
-                return Err(Error::TransactionSubmissionTimeout);
+            if start.elapsed() >= config.timeout {
+                return Err(Error::TransactionSubmissionTimeout(Box::new(response)));
+            }
+            sleep(delay).await;
+            if let Some(backoff) = config.backoff {
+                delay = (delay * 2).min(backoff.max);
             }
-            sleep(Duration::from_secs(1)).await;
         }
     }
 
@@ -214,8 +355,7 @@ impl Client {
     ) -> Result<SimulateTransactionResponse, Error> {
         let base64_tx = tx.to_xdr_base64()?;
         let response: SimulateTransactionResponse = self
-            .client()?
-            .request("simulateTransaction", rpc_params![base64_tx])
+            .request("simulateTransaction", serde_json::json!([base64_tx]))
             .await?;
         match response.error {
             None => Ok(response),
@@ -227,20 +367,17 @@ impl Client {
         &self,
         tx_id: &str,
     ) -> Result<GetTransactionStatusResponse, Error> {
-        Ok(self
-            .client()?
-            .request("getTransactionStatus", rpc_params![tx_id])
-            .await?)
+        self.request("getTransactionStatus", serde_json::json!([tx_id]))
+            .await
     }
 
     pub async fn get_ledger_entry(&self, key: LedgerKey) -> Result<GetLedgerEntryResponse, Error> {
         let base64_key = key.to_xdr_base64()?;
-        Ok(self
-            .client()?
-            .request("getLedgerEntry", rpc_params![base64_key])
-            .await?)
+        self.request("getLedgerEntry", serde_json::json!([base64_key]))
+            .await
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn get_events(
         &self,
         start_ledger: u32,
@@ -249,6 +386,7 @@ impl Client {
         contract_ids: &[String],
         topics: &[String],
         limit: Option<usize>,
+        cursor: Option<String>,
     ) -> Result<Option<GetEventsResponse>, Error> {
         let mut filters = serde_json::Map::new();
 
@@ -267,17 +405,263 @@ impl Client {
         if let Some(limit) = limit {
             pagination.insert("limit".to_string(), limit.into());
         }
-        // TODO: cursor
-
-        let mut object = collections::BTreeMap::<&str, jsonrpsee_core::JsonValue>::new();
-        object.insert("startLedger", start_ledger.to_string().into());
-        object.insert("endLedger", end_ledger.to_string().into());
-        object.insert("filters", vec![filters].into());
-        object.insert("pagination", pagination.into());
-
-        Ok(self
-            .client()?
-            .request("getEvents", Some(types::ParamsSer::Map(object)))
-            .await?)
+        if let Some(cursor) = cursor {
+            pagination.insert("cursor".to_string(), cursor.into());
+        }
+
+        let mut object = serde_json::Map::new();
+        object.insert("startLedger".to_string(), start_ledger.to_string().into());
+        object.insert("endLedger".to_string(), end_ledger.to_string().into());
+        object.insert("filters".to_string(), vec![filters].into());
+        object.insert("pagination".to_string(), pagination.into());
+
+        self.request("getEvents", serde_json::Value::Object(object))
+            .await
+    }
+
+    /// Stream every `Event` in `[start_ledger, end_ledger]`, transparently
+    /// following `pagingToken` cursors across pages so callers don't have to do
+    /// their own page bookkeeping.
+    ///
+    /// Each page is fetched with `page_limit` (the server's per-page `limit`);
+    /// the stream ends when a page comes back empty, a short page signals the
+    /// last page, or `total_limit` events have been yielded. When the server's
+    /// cursor is inclusive the boundary event repeated at the top of the next
+    /// page is deduped.
+    #[allow(clippy::too_many_arguments)]
+    pub fn get_events_paged(
+        &self,
+        start_ledger: u32,
+        end_ledger: u32,
+        event_type: Option<EventType>,
+        contract_ids: &[String],
+        topics: &[String],
+        page_limit: Option<usize>,
+        total_limit: Option<usize>,
+        cursor: Option<String>,
+    ) -> impl futures::stream::Stream<Item = Result<Event, Error>> + '_ {
+        let state = PagedEvents {
+            client: self,
+            start_ledger,
+            end_ledger,
+            event_type,
+            // Own the filters so the returned stream outlives the borrowed slices.
+            contract_ids: contract_ids.to_vec(),
+            topics: topics.to_vec(),
+            page_limit,
+            total_limit,
+            emitted: 0,
+            cursor,
+            buffer: std::collections::VecDeque::new(),
+            done: false,
+        };
+        futures::stream::unfold(state, PagedEvents::next)
+    }
+}
+
+/// Drives [`Client::get_events_paged`]: buffers one page at a time and replays
+/// it event by event, fetching the next page when the buffer drains.
+struct PagedEvents<'a> {
+    client: &'a Client,
+    start_ledger: u32,
+    end_ledger: u32,
+    event_type: Option<EventType>,
+    contract_ids: Vec<String>,
+    topics: Vec<String>,
+    page_limit: Option<usize>,
+    total_limit: Option<usize>,
+    emitted: usize,
+    cursor: Option<String>,
+    buffer: std::collections::VecDeque<Event>,
+    done: bool,
+}
+
+impl<'a> PagedEvents<'a> {
+    async fn next(mut self) -> Option<(Result<Event, Error>, Self)> {
+        loop {
+            if self.total_limit.is_some_and(|total| self.emitted >= total) {
+                return None;
+            }
+            if let Some(event) = self.buffer.pop_front() {
+                self.emitted += 1;
+                self.cursor = Some(event.paging_token.clone());
+                return Some((Ok(event), self));
+            }
+            if self.done {
+                return None;
+            }
+
+            let page = self
+                .client
+                .get_events(
+                    self.start_ledger,
+                    self.end_ledger,
+                    self.event_type,
+                    &self.contract_ids,
+                    &self.topics,
+                    self.page_limit,
+                    self.cursor.clone(),
+                )
+                .await;
+            match page {
+                Ok(Some(mut events)) => {
+                    // A short page means the server has nothing more to give.
+                    // Decide this from the raw page length, before the inclusive
+                    // cursor dedup below removes the boundary event — otherwise a
+                    // full page that dedups to `limit - 1` looks short and halts
+                    // pagination early.
+                    if self.page_limit.is_some_and(|limit| events.len() < limit) {
+                        self.done = true;
+                    }
+                    // Drop the event the cursor points at if the server repeats
+                    // it (an inclusive cursor); a no-op for exclusive cursors.
+                    if let Some(cursor) = &self.cursor {
+                        if events.first().is_some_and(|e| &e.paging_token == cursor) {
+                            events.remove(0);
+                        }
+                    }
+                    if events.is_empty() {
+                        self.done = true;
+                        return None;
+                    }
+                    self.buffer = events.into();
+                }
+                Ok(None) => {
+                    self.done = true;
+                    return None;
+                }
+                Err(e) => {
+                    self.done = true;
+                    return Some((Err(e), self));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_env_host::xdr::{
+        Memo, MuxedAccount, Preconditions, SequenceNumber, Transaction, TransactionExt,
+        TransactionV1Envelope, Uint256, VecM,
+    };
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// A minimal, unsigned envelope: the mock transport never inspects the XDR,
+    /// so any well-formed transaction serves to drive the submission loop.
+    fn dummy_tx() -> TransactionEnvelope {
+        TransactionEnvelope::Tx(TransactionV1Envelope {
+            tx: Transaction {
+                source_account: MuxedAccount::Ed25519(Uint256([0; 32])),
+                fee: 100,
+                seq_num: SequenceNumber(1),
+                cond: Preconditions::None,
+                memo: Memo::None,
+                operations: VecM::default(),
+                ext: TransactionExt::V0,
+            },
+            signatures: VecM::default(),
+        })
+    }
+
+    #[tokio::test]
+    async fn send_transaction_polls_status_until_success() {
+        let mut mocks = Mocks::new();
+        mocks.insert(
+            "sendTransaction".to_string(),
+            serde_json::json!({ "id": "abc", "status": "pending" }),
+        );
+        mocks.insert(
+            "getTransactionStatus".to_string(),
+            serde_json::json!({
+                "id": "abc",
+                "status": "success",
+                "results": [{ "xdr": "deadbeef" }],
+            }),
+        );
+        let client = Client::new_mock(mocks);
+
+        let seen = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&seen);
+        let results = client
+            .send_transaction_with_config(&dummy_tx(), &ConfirmationConfig::default(), move |_| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                async {}
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].xdr, "deadbeef");
+        assert_eq!(seen.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn send_transaction_maps_error_status() {
+        let mut mocks = Mocks::new();
+        mocks.insert(
+            "sendTransaction".to_string(),
+            serde_json::json!({ "id": "abc", "status": "pending" }),
+        );
+        mocks.insert(
+            "getTransactionStatus".to_string(),
+            serde_json::json!({ "id": "abc", "status": "error" }),
+        );
+        let client = Client::new_mock(mocks);
+
+        let err = client.send_transaction(&dummy_tx()).await.unwrap_err();
+        assert!(matches!(err, Error::TransactionSubmissionFailed));
+    }
+
+    #[tokio::test]
+    async fn simulate_transaction_maps_error() {
+        let mut mocks = Mocks::new();
+        mocks.insert(
+            "simulateTransaction".to_string(),
+            serde_json::json!({
+                "footprint": "",
+                "cost": { "cpuInsns": "0", "memBytes": "0" },
+                "error": "boom",
+            }),
+        );
+        let client = Client::new_mock(mocks);
+
+        let err = client.simulate_transaction(&dummy_tx()).await.unwrap_err();
+        assert!(matches!(err, Error::TransactionSimulationFailed(e) if e == "boom"));
+    }
+
+    #[tokio::test]
+    async fn get_events_serializes_params() {
+        // Register the response under the `"method:params"` key so the lookup
+        // only succeeds if `get_events` serializes exactly this request body.
+        let expected = serde_json::json!({
+            "startLedger": "100",
+            "endLedger": "200",
+            "filters": [{
+                "type": "contract",
+                "topics": ["t1"],
+                "contractIds": ["c1"],
+            }],
+            "pagination": { "limit": 5, "cursor": "cur" },
+        });
+        let mut mocks = Mocks::new();
+        mocks.insert(format!("getEvents:{expected}"), serde_json::json!([]));
+        let client = Client::new_mock(mocks);
+
+        let events = client
+            .get_events(
+                100,
+                200,
+                Some(EventType::Contract),
+                &["c1".to_string()],
+                &["t1".to_string()],
+                Some(5),
+                Some("cur".to_string()),
+            )
+            .await
+            .unwrap();
+        assert!(matches!(events, Some(v) if v.is_empty()));
     }
 }
\ No newline at end of file
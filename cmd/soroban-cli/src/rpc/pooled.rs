@@ -0,0 +1,256 @@
+use rand::Rng;
+use soroban_env_host::xdr::{LedgerKey, TransactionEnvelope};
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use super::{
+    Client, Error, EventType, GetAccountResponse, GetEventsResponse, GetLedgerEntryResponse,
+    GetTransactionStatusResponse, SimulateTransactionResponse, TransactionStatusResult,
+};
+
+/// Try the selected endpoints in order, recording health and failing over to the
+/// next backend on retryable errors.
+macro_rules! pooled {
+    ($self:ident, $selector:expr, |$client:ident| $call:expr) => {{
+        let mut last_err = None;
+        for idx in $self.select($selector) {
+            let endpoint = &$self.endpoints[idx];
+            let $client = &endpoint.client;
+            let started = Instant::now();
+            match $call.await {
+                Ok(value) => {
+                    endpoint.record_success(started.elapsed());
+                    return Ok(value);
+                }
+                Err(e) if is_retryable(&e) => {
+                    endpoint.record_failure();
+                    last_err = Some(e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Err(last_err.unwrap_or(Error::NoHealthyEndpoint))
+    }};
+}
+
+/// Mark an endpoint unhealthy after this many consecutive failures; it stays in
+/// the rotation as a last-resort fallback but is deprioritised until it answers.
+const MAX_CONSECUTIVE_FAILURES: u32 = 3;
+
+/// How far back in history an endpoint can answer, used to route archive vs.
+/// recent-ledger queries.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Retention {
+    /// A full-history node able to serve any ledger.
+    Archive,
+    /// A pruned node retaining ledgers back to `oldest_ledger`.
+    Pruned { oldest_ledger: u32 },
+}
+
+impl Retention {
+    fn can_serve(self, start_ledger: u32) -> bool {
+        match self {
+            Retention::Archive => true,
+            Retention::Pruned { oldest_ledger } => start_ledger >= oldest_ledger,
+        }
+    }
+
+    fn is_archive(self) -> bool {
+        matches!(self, Retention::Archive)
+    }
+}
+
+/// A single backend: its RPC [`Client`], retention tag, and rolling health.
+struct Endpoint {
+    client: Client,
+    retention: Retention,
+    health: Mutex<Health>,
+}
+
+#[derive(Default)]
+struct Health {
+    consecutive_failures: u32,
+    last_latency: Option<Duration>,
+}
+
+impl Endpoint {
+    fn is_healthy(&self) -> bool {
+        self.health.lock().unwrap().consecutive_failures < MAX_CONSECUTIVE_FAILURES
+    }
+
+    /// Relative preference when choosing among endpoints: healthier and
+    /// lower-latency backends weigh more.
+    fn weight(&self) -> f64 {
+        let health = self.health.lock().unwrap();
+        let latency_ms = health
+            .last_latency
+            .map_or(0.0, |d| d.as_secs_f64() * 1000.0);
+        1.0 / ((1.0 + f64::from(health.consecutive_failures)) * (1.0 + latency_ms))
+    }
+
+    fn record_success(&self, latency: Duration) {
+        let mut health = self.health.lock().unwrap();
+        health.consecutive_failures = 0;
+        health.last_latency = Some(latency);
+    }
+
+    fn record_failure(&self) {
+        self.health.lock().unwrap().consecutive_failures += 1;
+    }
+}
+
+/// Which endpoints a call may be routed to.
+#[derive(Clone, Copy)]
+enum Selector {
+    /// A recent-ledger query: any endpoint can serve it, but prefer pruned
+    /// nodes so archive nodes are spared for queries that actually need them.
+    Recent,
+    /// A query reaching back to `start_ledger`: only endpoints retaining it are
+    /// eligible, which naturally falls back to archive nodes for old ledgers.
+    Since(u32),
+}
+
+/// A [`Client`] spread over several RPC base URLs, with health-aware failover
+/// and weighted load balancing so a single node going down is survivable.
+pub struct PooledClient {
+    endpoints: Vec<Endpoint>,
+}
+
+impl PooledClient {
+    /// Build a pool from `(base_url, retention)` pairs.
+    pub fn new(endpoints: impl IntoIterator<Item = (String, Retention)>) -> Self {
+        Self {
+            endpoints: endpoints
+                .into_iter()
+                .map(|(url, retention)| Endpoint {
+                    client: Client::new(&url),
+                    retention,
+                    health: Mutex::new(Health::default()),
+                })
+                .collect(),
+        }
+    }
+
+    /// Produce the order in which endpoints should be tried for `selector`:
+    /// eligible-and-healthy first (preferring pruned over archive, weighted by
+    /// health within each tier), then the eligible but unhealthy endpoints as a
+    /// last resort. Endpoints that cannot serve `selector` are never tried.
+    fn select(&self, selector: Selector) -> Vec<usize> {
+        let eligible: Vec<usize> = (0..self.endpoints.len())
+            .filter(|&i| match selector {
+                Selector::Recent => true,
+                Selector::Since(start) => self.endpoints[i].retention.can_serve(start),
+            })
+            .collect();
+
+        let (healthy, unhealthy): (Vec<usize>, Vec<usize>) = eligible
+            .into_iter()
+            .partition(|&i| self.endpoints[i].is_healthy());
+
+        // Prefer non-archive nodes, so archive capacity is reserved for queries
+        // that genuinely need the history.
+        let (pruned, archive): (Vec<usize>, Vec<usize>) = healthy
+            .into_iter()
+            .partition(|&i| !self.endpoints[i].retention.is_archive());
+
+        let mut order = self.weighted_order(pruned);
+        order.extend(self.weighted_order(archive));
+        order.extend(self.weighted_order(unhealthy));
+        order
+    }
+
+    /// Weighted-random shuffle of `indices`: higher-weight endpoints tend to
+    /// come first, spreading load without pinning every call to one backend.
+    fn weighted_order(&self, mut indices: Vec<usize>) -> Vec<usize> {
+        let mut rng = rand::thread_rng();
+        let mut order = Vec::with_capacity(indices.len());
+        while !indices.is_empty() {
+            let weights: Vec<f64> = indices.iter().map(|&i| self.endpoints[i].weight()).collect();
+            let total: f64 = weights.iter().sum();
+            let pick = if total > 0.0 {
+                let mut target = rng.gen::<f64>() * total;
+                weights
+                    .iter()
+                    .position(|w| {
+                        target -= w;
+                        target <= 0.0
+                    })
+                    .unwrap_or(0)
+            } else {
+                0
+            };
+            order.push(indices.swap_remove(pick));
+        }
+        order
+    }
+
+    pub async fn get_account(&self, account_id: &str) -> Result<GetAccountResponse, Error> {
+        pooled!(self, Selector::Recent, |client| client
+            .get_account(account_id))
+    }
+
+    pub async fn send_transaction(
+        &self,
+        tx: &TransactionEnvelope,
+    ) -> Result<Vec<TransactionStatusResult>, Error> {
+        pooled!(self, Selector::Recent, |client| client.send_transaction(tx))
+    }
+
+    pub async fn simulate_transaction(
+        &self,
+        tx: &TransactionEnvelope,
+    ) -> Result<SimulateTransactionResponse, Error> {
+        pooled!(self, Selector::Recent, |client| client
+            .simulate_transaction(tx))
+    }
+
+    pub async fn get_transaction_status(
+        &self,
+        tx_id: &str,
+    ) -> Result<GetTransactionStatusResponse, Error> {
+        pooled!(self, Selector::Recent, |client| client
+            .get_transaction_status(tx_id))
+    }
+
+    pub async fn get_ledger_entry(&self, key: LedgerKey) -> Result<GetLedgerEntryResponse, Error> {
+        // `LedgerKey` isn't `Copy`, so hand each attempt its own clone.
+        pooled!(self, Selector::Recent, |client| client
+            .get_ledger_entry(key.clone()))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn get_events(
+        &self,
+        start_ledger: u32,
+        end_ledger: u32,
+        event_type: Option<EventType>,
+        contract_ids: &[String],
+        topics: &[String],
+        limit: Option<usize>,
+        cursor: Option<String>,
+    ) -> Result<Option<GetEventsResponse>, Error> {
+        pooled!(self, Selector::Since(start_ledger), |client| client.get_events(
+            start_ledger,
+            end_ledger,
+            event_type,
+            contract_ids,
+            topics,
+            limit,
+            cursor.clone()
+        ))
+    }
+}
+
+/// Only transport-level failures are retried on another backend. Errors that
+/// reflect a deterministic outcome of the request itself —
+/// `TransactionSimulationFailed` and `TransactionSubmissionFailed` (a tx that
+/// already reached `error` status) — are never failed over, since re-running
+/// them elsewhere would re-execute work that already resolved.
+fn is_retryable(error: &Error) -> bool {
+    matches!(
+        error,
+        Error::JsonRpc(_) | Error::Ws(_) | Error::WsClosed | Error::WsPongTimeout
+    )
+}
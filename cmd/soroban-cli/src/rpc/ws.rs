@@ -0,0 +1,332 @@
+use futures::{
+    sink::SinkExt,
+    stream::{Stream, StreamExt},
+};
+use std::{
+    collections,
+    pin::Pin,
+    sync::atomic::{AtomicU64, Ordering},
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::{
+    sync::{mpsc, oneshot},
+    time::{interval, sleep},
+};
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+
+use super::{Error, Event, GetTransactionStatusResponse};
+
+// The server is expected to answer a `subscribe*` call with a bare JSON-RPC
+// result carrying the subscription id, and to push notifications whose
+// `params.subscription` matches that id. This mirrors the ethers-rs pubsub
+// convention rather than the jsonrpsee subscription framing, because the
+// soroban RPC notification envelope is shaped the same way.
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(30);
+const PONG_TIMEOUT: Duration = Duration::from_secs(3);
+const RECONNECT_DELAY: Duration = Duration::from_secs(10);
+
+/// A live subscription request the background task must (re-)establish on the
+/// wire. We keep the originating params around so the connection can be rebuilt
+/// transparently after a reconnect.
+struct Subscription {
+    method: &'static str,
+    params: serde_json::Value,
+    sink: mpsc::UnboundedSender<serde_json::Value>,
+}
+
+enum Command {
+    Subscribe {
+        method: &'static str,
+        params: serde_json::Value,
+        sink: mpsc::UnboundedSender<serde_json::Value>,
+        ack: oneshot::Sender<Result<u64, Error>>,
+    },
+}
+
+/// Owns the WebSocket connection on a background task and fans server
+/// notifications out to per-subscription channels.
+///
+/// Cloning is cheap: every clone talks to the same background task through the
+/// command channel.
+#[derive(Clone)]
+pub struct WsClient {
+    commands: mpsc::UnboundedSender<Command>,
+}
+
+impl WsClient {
+    /// Connect to `url` and spawn the connection-owning task.
+    pub fn new(url: &str) -> Self {
+        let (commands, command_rx) = mpsc::unbounded_channel();
+        let manager = Manager {
+            url: url.to_string(),
+            command_rx,
+            subscriptions: collections::BTreeMap::new(),
+        };
+        tokio::spawn(manager.run());
+        Self { commands }
+    }
+
+    /// Subscribe to contract/system events matching `filters`, yielding each
+    /// `Event` as it is pushed by the server.
+    pub async fn subscribe_events(
+        &self,
+        filters: serde_json::Value,
+    ) -> Result<impl Stream<Item = Result<Event, Error>>, Error> {
+        self.subscribe("subscribeEvents", filters).await
+    }
+
+    /// Subscribe to the status of a single in-flight transaction, yielding each
+    /// `GetTransactionStatusResponse` as the server reports progress.
+    pub async fn subscribe_transaction_status(
+        &self,
+        id: &str,
+    ) -> Result<impl Stream<Item = Result<GetTransactionStatusResponse, Error>>, Error> {
+        self.subscribe("subscribeTransactionStatus", serde_json::json!([id]))
+            .await
+    }
+
+    async fn subscribe<T>(
+        &self,
+        method: &'static str,
+        params: serde_json::Value,
+    ) -> Result<impl Stream<Item = Result<T, Error>>, Error>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let (sink, stream) = mpsc::unbounded_channel();
+        let (ack, ack_rx) = oneshot::channel();
+        self.commands
+            .send(Command::Subscribe {
+                method,
+                params,
+                sink,
+                ack,
+            })
+            .map_err(|_| Error::WsClosed)?;
+        ack_rx.await.map_err(|_| Error::WsClosed)??;
+        Ok(SubscriptionStream {
+            inner: stream,
+            _marker: std::marker::PhantomData,
+        })
+    }
+}
+
+/// A `Stream` wrapping the receiving half of a subscription channel,
+/// deserializing each notification into `T`.
+struct SubscriptionStream<T> {
+    inner: mpsc::UnboundedReceiver<serde_json::Value>,
+    _marker: std::marker::PhantomData<T>,
+}
+
+impl<T> Stream for SubscriptionStream<T>
+where
+    T: serde::de::DeserializeOwned,
+{
+    type Item = Result<T, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match self.inner.poll_recv(cx) {
+            Poll::Ready(Some(value)) => {
+                Poll::Ready(Some(serde_json::from_value(value).map_err(Error::Serde)))
+            }
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+struct Manager {
+    url: String,
+    command_rx: mpsc::UnboundedReceiver<Command>,
+    // wire subscription id -> delivery channel
+    subscriptions: collections::BTreeMap<u64, Subscription>,
+}
+
+impl Manager {
+    async fn run(mut self) {
+        loop {
+            if let Err(e) = self.run_connection().await {
+                // The connection died (dead pong, transport error, or a clean
+                // close). Drop any sinks that have no receiver left, keep the
+                // rest, and reconnect after a short delay, re-establishing the
+                // outstanding subscriptions.
+                let _ = e;
+                self.subscriptions.retain(|_, sub| !sub.sink.is_closed());
+                if self.subscriptions.is_empty() && self.command_rx.is_closed() {
+                    return;
+                }
+                sleep(RECONNECT_DELAY).await;
+            } else {
+                // Clean shutdown: the command channel closed (every `WsClient`
+                // handle was dropped). Any subscriptions still in the map are
+                // dropped with the manager, which ends their streams.
+                return;
+            }
+        }
+    }
+
+    async fn run_connection(&mut self) -> Result<(), Error> {
+        let (mut ws, _) = connect_async(&self.url)
+            .await
+            .map_err(|e| Error::Ws(e.to_string()))?;
+
+        // Re-establish any subscriptions that survived a previous connection,
+        // remapping them onto the ids the server assigns this time around.
+        let mut pending = std::mem::take(&mut self.subscriptions).into_values();
+        while let Some(sub) = pending.next() {
+            match self.establish(&mut ws, sub.method, &sub.params).await {
+                Ok(sub_id) => {
+                    self.subscriptions.insert(sub_id, sub);
+                }
+                Err(e) => {
+                    // Keep every subscription for the next attempt; the keys are
+                    // placeholders until the server assigns fresh ids.
+                    let kept: Vec<Subscription> = std::mem::take(&mut self.subscriptions)
+                        .into_values()
+                        .chain(std::iter::once(sub))
+                        .chain(pending)
+                        .collect();
+                    self.subscriptions = (0..).zip(kept).collect();
+                    return Err(e);
+                }
+            }
+        }
+
+        let mut keepalive = interval(KEEPALIVE_INTERVAL);
+        keepalive.tick().await; // consume the immediate first tick
+
+        // A single `select!` loop keeps dispatching event notifications while a
+        // keepalive ping is outstanding: we arm `pong_deadline` when the ping
+        // goes out and treat a pong that arrives first as proof the connection
+        // is alive. This is what lets a `Message::Text` pushed
+        // inside the `PONG_TIMEOUT` window reach subscribers instead of being
+        // drained away.
+        let pong_deadline = sleep(PONG_TIMEOUT);
+        tokio::pin!(pong_deadline);
+        let mut awaiting_pong = false;
+
+        loop {
+            tokio::select! {
+                cmd = self.command_rx.recv() => match cmd {
+                    Some(Command::Subscribe { method, params, sink, ack }) => {
+                        // Only ack once the server has accepted the subscription
+                        // and handed back the real id we route notifications on.
+                        match self.establish(&mut ws, method, &params).await {
+                            Ok(sub_id) => {
+                                self.subscriptions.insert(sub_id, Subscription { method, params, sink });
+                                let _ = ack.send(Ok(sub_id));
+                            }
+                            Err(e) => {
+                                let _ = ack.send(Err(e));
+                            }
+                        }
+                    }
+                    None => return Ok(()),
+                },
+                msg = ws.next() => match msg {
+                    Some(Ok(Message::Text(text))) => self.dispatch(&text),
+                    Some(Ok(Message::Pong(_))) => awaiting_pong = false,
+                    Some(Ok(Message::Ping(_))) => {}
+                    Some(Ok(Message::Close(_))) | None => return Err(Error::WsClosed),
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => return Err(Error::Ws(e.to_string())),
+                },
+                _ = keepalive.tick() => {
+                    ws.send(Message::Ping(Vec::new())).await.map_err(|e| Error::Ws(e.to_string()))?;
+                    // Arm the pong watchdog. A healthy peer answers promptly; a
+                    // missing pong means the connection is dead and we reconnect.
+                    awaiting_pong = true;
+                    pong_deadline.as_mut().reset(tokio::time::Instant::now() + PONG_TIMEOUT);
+                }
+                () = &mut pong_deadline, if awaiting_pong => return Err(Error::WsPongTimeout),
+            }
+        }
+    }
+
+    /// Send a subscribe request and wait for the server's result frame, which
+    /// carries the subscription id that notifications are keyed on — a separate
+    /// namespace from the client-generated request id. Notifications for other
+    /// subscriptions that arrive before the result are dispatched in the
+    /// meantime so none are lost during the handshake.
+    async fn establish(
+        &mut self,
+        ws: &mut (impl SinkExt<Message, Error = tokio_tungstenite::tungstenite::Error>
+                  + StreamExt<Item = Result<Message, tokio_tungstenite::tungstenite::Error>>
+                  + Unpin),
+        method: &'static str,
+        params: &serde_json::Value,
+    ) -> Result<u64, Error> {
+        let request_id = self.send_subscribe(ws, method, params).await?;
+        while let Some(msg) = ws.next().await {
+            match msg {
+                Ok(Message::Text(text)) => {
+                    let Ok(value) = serde_json::from_str::<serde_json::Value>(&text) else {
+                        continue;
+                    };
+                    // Correlate by JSON-RPC id: this frame is our subscribe
+                    // response. Its `result` is the server-assigned id.
+                    if value.get("id").and_then(serde_json::Value::as_u64) == Some(request_id) {
+                        return value
+                            .get("result")
+                            .and_then(serde_json::Value::as_u64)
+                            .ok_or_else(|| Error::Ws(format!("subscribe rejected: {text}")));
+                    }
+                    self.dispatch_value(&value);
+                }
+                Ok(Message::Ping(_) | Message::Pong(_)) => {}
+                Ok(Message::Close(_)) => return Err(Error::WsClosed),
+                Ok(_) => {}
+                Err(e) => return Err(Error::Ws(e.to_string())),
+            }
+        }
+        Err(Error::WsClosed)
+    }
+
+    async fn send_subscribe(
+        &self,
+        ws: &mut (impl SinkExt<Message, Error = tokio_tungstenite::tungstenite::Error> + Unpin),
+        method: &'static str,
+        params: &serde_json::Value,
+    ) -> Result<u64, Error> {
+        // A monotonic request id is fine here: the manager is single-threaded.
+        static REQUEST_ID: AtomicU64 = AtomicU64::new(1);
+        let request_id = REQUEST_ID.fetch_add(1, Ordering::Relaxed);
+        let req = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": request_id,
+            "method": method,
+            "params": params,
+        });
+        ws.send(Message::Text(req.to_string()))
+            .await
+            .map_err(|e| Error::Ws(e.to_string()))?;
+        Ok(request_id)
+    }
+
+    /// Route a text frame to the matching subscription channel, if any. We
+    /// tolerate unknown subscription ids so that a late notification for a
+    /// dropped subscription is quietly discarded.
+    fn dispatch(&mut self, text: &str) {
+        let Ok(value) = serde_json::from_str::<serde_json::Value>(text) else {
+            return;
+        };
+        self.dispatch_value(&value);
+    }
+
+    fn dispatch_value(&mut self, value: &serde_json::Value) {
+        let Some(params) = value.get("params") else {
+            return;
+        };
+        let Some(id) = params.get("subscription").and_then(serde_json::Value::as_u64) else {
+            return;
+        };
+        if let Some(sub) = self.subscriptions.get(&id) {
+            if let Some(result) = params.get("result") {
+                // If the receiver is gone the send fails; the dead subscription
+                // is reaped on the next reconnect.
+                let _ = sub.sink.send(result.clone());
+            }
+        }
+    }
+}